@@ -1,92 +1,487 @@
-use eyre::{Context, Result};
+use eyre::Result;
 use serde::Serialize;
 use std::fs::{self, File};
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::Mutex;
+use tauri::State;
 use tauri_plugin_dialog::DialogExt;
-use walkdir::WalkDir;
 
-#[derive(Serialize)]
+#[derive(Serialize, Clone)]
 pub struct FileMetadata {
     pub path: String,
     pub name: String,
     pub language: Option<String>,
+    pub status: Option<crate::vcs::FileStatus>,
+}
+
+/// Structured error surfaced by the file-access subsystem so the frontend can
+/// distinguish a boundary violation from an ordinary IO failure.
+#[derive(Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "kebab-case")]
+pub enum FsError {
+    PermissionDenied(String),
+    Io(String),
+}
+
+impl FsError {
+    fn io(err: impl std::fmt::Display) -> Self {
+        FsError::Io(err.to_string())
+    }
+}
+
+/// Capability layer guarding every filesystem command: requested paths must
+/// resolve inside one of the granted roots before any access is performed.
+pub struct WorkspaceAccess {
+    roots: Mutex<Vec<PathBuf>>,
+}
+
+impl WorkspaceAccess {
+    /// Seed the workspace with its canonicalized root, falling back to the
+    /// process working directory when none is supplied or it can't be resolved.
+    pub fn new(root: Option<String>) -> Self {
+        let root = root
+            .map(PathBuf::from)
+            .and_then(|p| p.canonicalize().ok())
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_else(|| PathBuf::from("."));
+        WorkspaceAccess {
+            roots: Mutex::new(vec![root]),
+        }
+    }
+
+    /// The first (primary) workspace root, used as the default listing base.
+    fn primary_root(&self) -> PathBuf {
+        self.roots
+            .lock()
+            .unwrap()
+            .first()
+            .cloned()
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    fn grant(&self, root: PathBuf) {
+        let mut roots = self.roots.lock().unwrap();
+        if !roots.contains(&root) {
+            roots.push(root);
+        }
+    }
+
+    /// Canonicalize `path` (resolving `..` and symlinks) and reject it if it
+    /// escapes every granted root. For paths that don't exist yet — e.g. a file
+    /// about to be created in a new nested subfolder — the nearest existing
+    /// ancestor is canonicalized and the non-existent tail re-appended, so the
+    /// boundary check still holds while allowing "save as" into fresh dirs.
+    fn resolve(&self, path: &str) -> Result<PathBuf, FsError> {
+        let requested = Path::new(path);
+        let canonical = match requested.canonicalize() {
+            Ok(p) => p,
+            Err(_) => {
+                // Walk up to the first ancestor that exists on disk.
+                let mut existing = requested;
+                let mut tail: Vec<&std::ffi::OsStr> = Vec::new();
+                let anchor = loop {
+                    match existing.canonicalize() {
+                        Ok(p) => break p,
+                        Err(_) => {
+                            let name = existing.file_name().ok_or_else(|| {
+                                FsError::PermissionDenied(format!(
+                                    "Cannot resolve path: {}",
+                                    path
+                                ))
+                            })?;
+                            tail.push(name);
+                            match existing.parent().filter(|p| !p.as_os_str().is_empty()) {
+                                Some(parent) => existing = parent,
+                                // A bare relative name (e.g. `newfile.txt`) has
+                                // no usable parent; anchor it at the CWD.
+                                None => {
+                                    break Path::new(".").canonicalize().map_err(|e| {
+                                        FsError::PermissionDenied(format!(
+                                            "Cannot resolve path: {}: {}",
+                                            path, e
+                                        ))
+                                    })?
+                                }
+                            }
+                        }
+                    }
+                };
+                // `tail` was collected leaf-first; re-append in path order.
+                let mut resolved = anchor;
+                for component in tail.into_iter().rev() {
+                    resolved.push(component);
+                }
+                resolved
+            }
+        };
+
+        let roots = self.roots.lock().unwrap();
+        if roots.iter().any(|root| canonical.starts_with(root)) {
+            Ok(canonical)
+        } else {
+            Err(FsError::PermissionDenied(format!(
+                "Path {} is outside the workspace",
+                path
+            )))
+        }
+    }
 }
 
 #[tauri::command]
-pub async fn list_files() -> Result<Vec<FileMetadata>, String> {
-    let mut files = Vec::new();
-    for entry in WalkDir::new(".").into_iter().filter_map(|e| e.ok()) {
-        if entry.file_type().is_file() {
-            let path = entry.path().to_path_buf();
+pub async fn allow_path(workspace: State<'_, WorkspaceAccess>, root: String) -> Result<(), FsError> {
+    let canonical = Path::new(&root)
+        .canonicalize()
+        .map_err(|e| FsError::PermissionDenied(format!("Cannot grant {}: {}", root, e)))?;
+    workspace.grant(canonical);
+    Ok(())
+}
+
+/// Resolve a canonical language id for a file from its extension, falling back
+/// to a `#!` shebang on the first line for extensionless scripts. `first_bytes`
+/// should hold the start of the file; pass an empty slice to skip the shebang
+/// probe.
+pub(crate) fn detect_language(path: &Path, first_bytes: &[u8]) -> Option<String> {
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        let lang = match ext.to_ascii_lowercase().as_str() {
+            "rs" => "rust",
+            "ts" | "tsx" => "typescript",
+            "js" | "jsx" | "mjs" | "cjs" => "javascript",
+            "py" | "pyi" => "python",
+            "go" => "go",
+            "c" | "h" => "c",
+            "cpp" | "cc" | "cxx" | "hpp" | "hh" => "cpp",
+            "rb" => "ruby",
+            "java" => "java",
+            "sh" | "bash" => "shell",
+            "json" => "json",
+            "toml" => "toml",
+            "yaml" | "yml" => "yaml",
+            "md" | "markdown" => "markdown",
+            "html" | "htm" => "html",
+            "css" => "css",
+            _ => return None,
+        };
+        return Some(lang.to_string());
+    }
+
+    detect_language_from_shebang(first_bytes)
+}
+
+/// Map the interpreter named in a `#!` line to a language id.
+fn detect_language_from_shebang(first_bytes: &[u8]) -> Option<String> {
+    let first_line = first_bytes.split(|&b| b == b'\n').next()?;
+    let first_line = std::str::from_utf8(first_line).ok()?.trim();
+    let rest = first_line.strip_prefix("#!")?;
+    // Resolve the interpreter, skipping a leading `/usr/bin/env`.
+    let mut tokens = rest.split_whitespace();
+    let mut interp = tokens.next()?;
+    let interp_name = Path::new(interp).file_name().and_then(|n| n.to_str())?;
+    if interp_name == "env" {
+        interp = tokens.next()?;
+    }
+    let interp_name = Path::new(interp).file_name().and_then(|n| n.to_str())?;
+    let lang = match interp_name {
+        n if n.starts_with("python") => "python",
+        n if n.starts_with("node") => "javascript",
+        n if n.starts_with("ruby") => "ruby",
+        "sh" | "bash" | "zsh" | "dash" => "shell",
+        _ => return None,
+    };
+    Some(lang.to_string())
+}
+
+/// Read the first few bytes of a file for shebang detection, returning an empty
+/// buffer if it can't be opened.
+pub(crate) fn read_first_bytes(path: &Path) -> Vec<u8> {
+    use std::io::Read;
+    let mut buf = Vec::new();
+    if let Ok(mut f) = File::open(path) {
+        let _ = f.take(256).read_to_end(&mut buf);
+    }
+    buf
+}
+
+/// Batch of indexed files streamed to the frontend for a single `list_files`
+/// request; `done` marks the terminating (possibly empty) chunk.
+#[derive(Serialize, Clone)]
+struct FileIndexChunk {
+    request_id: String,
+    files: Vec<FileMetadata>,
+    done: bool,
+}
+
+/// Number of entries accumulated before a `file-index-chunk` is emitted.
+const INDEX_CHUNK_SIZE: usize = 128;
+
+/// Directories skipped regardless of ignore rules, so huge build/dependency
+/// trees never reach the walker even when they aren't gitignored.
+const PRUNED_DIRS: &[&str] = &[".git", "node_modules", "target"];
+
+/// Case-insensitive subsequence match, so `fsr` matches `file_system.rs`.
+fn matches_query(haystack: &str, query: &str) -> bool {
+    if query.is_empty() {
+        return true;
+    }
+    let mut chars = haystack.chars().map(|c| c.to_ascii_lowercase());
+    query
+        .chars()
+        .map(|c| c.to_ascii_lowercase())
+        .all(|needle| chars.any(|c| c == needle))
+}
+
+#[tauri::command]
+pub async fn list_files(
+    app: tauri::AppHandle,
+    workspace: State<'_, WorkspaceAccess>,
+    request_id: String,
+    query: Option<String>,
+) -> Result<(), FsError> {
+    use ignore::{WalkBuilder, WalkState};
+    use std::sync::Arc;
+    use tauri::Emitter;
+
+    let root = workspace.primary_root();
+    let query = query.unwrap_or_default();
+
+    // Shared buffer flushed in batches from the parallel walker threads.
+    let buffer = Arc::new(Mutex::new(Vec::<FileMetadata>::new()));
+    let app = Arc::new(app);
+    let request_id = Arc::new(request_id);
+    let query = Arc::new(query);
+
+    let walker = WalkBuilder::new(&root)
+        .hidden(false)
+        .git_ignore(true)
+        .git_global(true)
+        .git_exclude(true)
+        .filter_entry(|entry| {
+            !(entry.file_type().map(|t| t.is_dir()).unwrap_or(false)
+                && entry
+                    .file_name()
+                    .to_str()
+                    .map(|n| PRUNED_DIRS.contains(&n))
+                    .unwrap_or(false))
+        })
+        .build_parallel();
+
+    walker.run(|| {
+        let buffer = buffer.clone();
+        let app = app.clone();
+        let request_id = request_id.clone();
+        let query = query.clone();
+        Box::new(move |entry| {
+            let entry = match entry {
+                Ok(e) => e,
+                Err(_) => return WalkState::Continue,
+            };
+            if !entry.file_type().map(|t| t.is_file()).unwrap_or(false) {
+                return WalkState::Continue;
+            }
+            let path = entry.path();
             let name = entry.file_name().to_string_lossy().to_string();
-            files.push(FileMetadata {
+            if !matches_query(&path.to_string_lossy(), &query) {
+                return WalkState::Continue;
+            }
+            let first_bytes = if path.extension().is_none() {
+                read_first_bytes(path)
+            } else {
+                Vec::new()
+            };
+            let language = detect_language(path, &first_bytes);
+            let meta = FileMetadata {
                 path: path.to_string_lossy().to_string(),
                 name,
-                language: None,
-            });
-            if files.len() >= 100 {
-                break;
+                language,
+                status: None,
+            };
+
+            let mut buf = buffer.lock().unwrap();
+            buf.push(meta);
+            if buf.len() >= INDEX_CHUNK_SIZE {
+                let files = std::mem::take(&mut *buf);
+                let _ = app.emit(
+                    "file-index-chunk",
+                    FileIndexChunk {
+                        request_id: (*request_id).clone(),
+                        files,
+                        done: false,
+                    },
+                );
             }
-        }
-    }
-    Ok(files)
+            WalkState::Continue
+        })
+    });
+
+    // Flush the remainder and signal completion.
+    let files = std::mem::take(&mut *buffer.lock().unwrap());
+    app.emit(
+        "file-index-chunk",
+        FileIndexChunk {
+            request_id: (*request_id).clone(),
+            files,
+            done: true,
+        },
+    )
+    .map_err(FsError::io)?;
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn list_git_files() -> Result<Vec<FileMetadata>, String> {
-    let output = Command::new("git")
-        .arg("ls-files")
-        .output()
-        .map_err(|e| e.to_string())?;
-    if !output.status.success() {
-        return Err("Failed to run git ls-files".to_string());
-    }
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut files = Vec::new();
-    for line in stdout.lines().take(100) {
-        let path = line.to_string();
-        let name = std::path::Path::new(&path)
-            .file_name()
-            .map(|n| n.to_string_lossy().to_string())
-            .unwrap_or_else(|| path.clone());
-        files.push(FileMetadata {
-            path,
-            name,
-            language: None,
-        });
-    }
-    Ok(files)
+pub async fn list_git_files(
+    workspace: State<'_, WorkspaceAccess>,
+) -> Result<Vec<FileMetadata>, String> {
+    use crate::vcs::VcsBackend;
+    let backend = crate::vcs::GitBackend::discover_at(&workspace.primary_root())?;
+    backend.tracked_files()
 }
 
 #[tauri::command]
-pub async fn save_file(path: &str, content: &str) -> Result<(), String> {
-    let path = Path::new(path);
+pub async fn save_file(
+    workspace: State<'_, WorkspaceAccess>,
+    path: &str,
+    content: &str,
+) -> Result<(), FsError> {
+    let path = workspace.resolve(path)?;
     if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+        fs::create_dir_all(parent).map_err(FsError::io)?;
     }
     let tmp_path = path.with_extension("tmp~");
     {
-        let mut tmp_file =
-            File::create(&tmp_path).map_err(|e| format!("Failed to create temp file: {}", e))?;
+        let mut tmp_file = File::create(&tmp_path).map_err(FsError::io)?;
         tmp_file
             .write_all(content.as_bytes())
-            .map_err(|e| format!("Failed to write to temp file: {}", e))?;
-        tmp_file
-            .sync_all()
-            .map_err(|e| format!("Failed to sync temp file: {}", e))?;
+            .map_err(FsError::io)?;
+        tmp_file.sync_all().map_err(FsError::io)?;
     }
-    fs::rename(&tmp_path, path)
-        .map_err(|e| format!("Failed to move temp file into place: {}", e))?;
+    fs::rename(&tmp_path, &path).map_err(FsError::io)?;
     Ok(())
 }
 
 #[tauri::command]
-pub async fn read_file(path: &str) -> Result<String, String> {
-    fs::read_to_string(path)
-        .wrap_err_with(|| format!("Failed to read file: {}", path))
-        .map_err(|e| e.to_string())
+pub async fn read_file(workspace: State<'_, WorkspaceAccess>, path: &str) -> Result<String, FsError> {
+    let path = workspace.resolve(path)?;
+    fs::read_to_string(path).map_err(FsError::io)
+}
+
+/// PATH-like variables that a sandbox runtime (AppImage/Flatpak/Snap) tends to
+/// prepend its own entries onto; cleaned before launching external programs.
+const PATH_LIKE_VARS: &[&str] = &["PATH", "LD_LIBRARY_PATH", "GST_PLUGIN_PATH", "XDG_DATA_DIRS"];
+
+/// A directory prefix injected by the active sandbox. `raw` prefixes (e.g. the
+/// synthetic AppImage mount marker) are matched as a literal string prefix;
+/// non-`raw` ones are real host paths matched on a path-component boundary.
+struct SandboxPrefix {
+    value: String,
+    raw: bool,
+}
+
+/// Return the prefixes the active sandbox injects into PATH-like variables, or
+/// an empty list when jeditr is not running inside one.
+fn sandbox_prefixes() -> Vec<SandboxPrefix> {
+    let mut prefixes = Vec::new();
+    if let Ok(appdir) = std::env::var("APPDIR") {
+        prefixes.push(SandboxPrefix {
+            value: appdir,
+            raw: false,
+        });
+    }
+    if std::env::var_os("APPIMAGE").is_some() {
+        // AppImages mount under /tmp/.mount_<id> even when APPDIR is unset; this
+        // is a partial marker, not a complete path, so match it literally.
+        prefixes.push(SandboxPrefix {
+            value: "/tmp/.mount_".to_string(),
+            raw: true,
+        });
+    }
+    if std::env::var_os("FLATPAK_ID").is_some() {
+        prefixes.push(SandboxPrefix {
+            value: "/app".to_string(),
+            raw: false,
+        });
+    }
+    if let Ok(snap) = std::env::var("SNAP") {
+        prefixes.push(SandboxPrefix { value: snap, raw: false });
+    }
+    prefixes
+}
+
+/// Remove sandbox-injected entries from a `:`-separated PATH-like value and
+/// de-duplicate the rest while preserving order.
+fn clean_path_like(value: &str, prefixes: &[SandboxPrefix]) -> String {
+    let mut seen = std::collections::HashSet::new();
+    value
+        .split(':')
+        .filter(|entry| !entry.is_empty())
+        .filter(|entry| !prefixes.iter().any(|p| under_prefix(entry, p)))
+        .filter(|entry| seen.insert(entry.to_string()))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Whether `entry` should be stripped for `prefix`. Non-`raw` prefixes match on
+/// a path-component boundary so `/app` does not strip an unrelated
+/// `/application/bin`; `raw` markers match as a literal string prefix.
+fn under_prefix(entry: &str, prefix: &SandboxPrefix) -> bool {
+    if prefix.raw {
+        return entry.starts_with(&prefix.value);
+    }
+    let base = prefix.value.trim_end_matches('/');
+    entry == base || entry.starts_with(&format!("{}/", base))
+}
+
+/// Build the environment external programs should see: the inherited
+/// environment with sandbox entries stripped from PATH-like variables, so a
+/// bundled build doesn't leak its runtime into the launched application.
+fn normalized_env() -> Vec<(String, String)> {
+    let prefixes = sandbox_prefixes();
+    std::env::vars()
+        .map(|(key, value)| {
+            if !prefixes.is_empty() && PATH_LIKE_VARS.contains(&key.as_str()) {
+                (key, clean_path_like(&value, &prefixes))
+            } else {
+                (key, value)
+            }
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn open_with(path: String, app: Option<String>) -> Result<(), String> {
+    let mut command = match &app {
+        Some(app) => {
+            let mut c = Command::new(app);
+            c.arg(&path);
+            c
+        }
+        None => {
+            #[cfg(target_os = "windows")]
+            {
+                let mut c = Command::new("cmd");
+                c.args(["/C", "start", "", &path]);
+                c
+            }
+            #[cfg(target_os = "macos")]
+            {
+                let mut c = Command::new("open");
+                c.arg(&path);
+                c
+            }
+            #[cfg(all(unix, not(target_os = "macos")))]
+            {
+                let mut c = Command::new("xdg-open");
+                c.arg(&path);
+                c
+            }
+        }
+    };
+
+    command.env_clear().envs(normalized_env());
+    command
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to open {}: {}", path, e))
 }
 
 #[tauri::command]
@@ -102,3 +497,91 @@ pub async fn open_file(app: tauri::AppHandle) -> Result<String, String> {
         None => Err("No file selected".to_string()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn boundary(value: &str) -> SandboxPrefix {
+        SandboxPrefix {
+            value: value.to_string(),
+            raw: false,
+        }
+    }
+
+    #[test]
+    fn clean_path_like_strips_sandbox_entries_and_dedups() {
+        let prefixes = [boundary("/app")];
+        let cleaned = clean_path_like("/app/bin:/usr/bin:/usr/bin:/bin", &prefixes);
+        assert_eq!(cleaned, "/usr/bin:/bin");
+    }
+
+    #[test]
+    fn clean_path_like_respects_component_boundary() {
+        let prefixes = [boundary("/app")];
+        // `/application/bin` must survive: `/app` is not a path-component prefix of it.
+        let cleaned = clean_path_like("/app/bin:/application/bin", &prefixes);
+        assert_eq!(cleaned, "/application/bin");
+    }
+
+    #[test]
+    fn clean_path_like_raw_marker_matches_partial_prefix() {
+        let prefixes = [SandboxPrefix {
+            value: "/tmp/.mount_".to_string(),
+            raw: true,
+        }];
+        let cleaned = clean_path_like("/tmp/.mount_abc123/usr/bin:/usr/bin", &prefixes);
+        assert_eq!(cleaned, "/usr/bin");
+    }
+
+    #[test]
+    fn clean_path_like_without_prefixes_only_dedups() {
+        let cleaned = clean_path_like("/usr/bin:/bin:/usr/bin", &[]);
+        assert_eq!(cleaned, "/usr/bin:/bin");
+    }
+
+    #[test]
+    fn detect_language_maps_extensions() {
+        assert_eq!(
+            detect_language(Path::new("src/main.rs"), b""),
+            Some("rust".to_string())
+        );
+        assert_eq!(
+            detect_language(Path::new("app.TSX"), b""),
+            Some("typescript".to_string())
+        );
+        assert_eq!(detect_language(Path::new("data.bin"), b""), None);
+    }
+
+    #[test]
+    fn detect_language_falls_back_to_shebang() {
+        assert_eq!(
+            detect_language(Path::new("scripts/deploy"), b"#!/usr/bin/env python3\n..."),
+            Some("python".to_string())
+        );
+        assert_eq!(
+            detect_language(Path::new("run"), b"#!/bin/bash\necho hi"),
+            Some("shell".to_string())
+        );
+    }
+
+    #[test]
+    fn detect_language_from_shebang_ignores_non_scripts() {
+        assert_eq!(detect_language_from_shebang(b"plain text"), None);
+        assert_eq!(detect_language_from_shebang(b"#!/usr/bin/unknown"), None);
+    }
+
+    #[test]
+    fn matches_query_is_case_insensitive_subsequence() {
+        assert!(matches_query("src/file_system.rs", "fsr"));
+        assert!(matches_query("src/File_System.rs", "FSR"));
+        assert!(matches_query("anything", ""));
+    }
+
+    #[test]
+    fn matches_query_rejects_non_subsequence() {
+        assert!(!matches_query("src/main.rs", "xyz"));
+        // Order matters for a subsequence.
+        assert!(!matches_query("abc", "cba"));
+    }
+}
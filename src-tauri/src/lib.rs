@@ -3,6 +3,7 @@ use window_vibrancy::*;
 
 mod file_system;
 mod terminal;
+mod vcs;
 
 pub fn run_with_args(working_dir: Option<String>) {
     tauri::Builder::default()
@@ -22,18 +23,24 @@ pub fn run_with_args(working_dir: Option<String>) {
             // Store the working directory in the app state for later use
             if let Some(dir) = &working_dir {
                 window.set_title(&format!("jeditr - {}", dir)).ok();
-                app.manage(dir.clone());
             }
+            // Seed the workspace access capability from the working directory.
+            app.manage(file_system::WorkspaceAccess::new(working_dir.clone()));
 
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             file_system::open_file,
+            file_system::open_with,
+            file_system::allow_path,
             file_system::read_file,
             file_system::save_file,
             file_system::list_files,
             file_system::list_git_files,
             terminal::start_shell,
+            terminal::resize_shell,
+            terminal::list_shells,
+            terminal::attach_shell,
             terminal::send_input,
             terminal::close_shell
         ])
@@ -1,7 +1,8 @@
+use base64::Engine;
 use dashmap::DashMap;
 use once_cell::sync::OnceCell;
-use std::io::{BufRead, BufReader, Write};
-use std::process::{Child, ChildStdin, Command, Stdio};
+use portable_pty::{Child, CommandBuilder, MasterPty, NativePtySystem, PtySize, PtySystem};
+use std::io::{Read, Write};
 use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Emitter};
 
@@ -9,9 +10,16 @@ use serde::Serialize;
 
 type SessionId = String;
 
+/// Upper bound on the per-session scrollback kept for `attach_shell`, so a
+/// long-running shell can't grow the buffer without limit.
+const SCROLLBACK_LIMIT: usize = 256 * 1024;
+
 pub struct TerminalSession {
-    pub child: Arc<Mutex<Child>>,
-    pub stdin: Arc<Mutex<ChildStdin>>,
+    pub child: Arc<Mutex<Box<dyn Child + Send + Sync>>>,
+    pub master: Arc<Mutex<Box<dyn MasterPty + Send>>>,
+    pub writer: Arc<Mutex<Box<dyn Write + Send>>>,
+    /// Raw bytes emitted so far, replayed when a tab reattaches.
+    pub scrollback: Arc<Mutex<Vec<u8>>>,
 }
 
 static SESSIONS: OnceCell<DashMap<SessionId, Arc<TerminalSession>>> = OnceCell::new();
@@ -68,51 +76,86 @@ struct ShellExit {
     exit_status: Option<i32>,
 }
 
+#[derive(Serialize, Clone)]
+pub struct ShellInfo {
+    pub session_id: SessionId,
+    pub pid: Option<u32>,
+    pub alive: bool,
+}
+
 #[tauri::command]
-pub async fn start_shell(app: AppHandle, session_id: String) {
+pub async fn start_shell(app: AppHandle, session_id: String) -> Result<(), String> {
     // Prevent duplicate shells for the same session ID
     if get_sessions().contains_key(&session_id) {
-        println!(
-            "Shell for session_id {} already exists, skipping spawn.",
+        return Err(format!(
+            "Shell for session_id {} already exists",
             session_id
-        );
-        return;
+        ));
     }
 
     let (shell, args) = detect_user_shell_and_args();
     println!("Spawning shell: {:?} {:?}", shell, args);
-    let mut child = Command::new(&shell)
-        .args(&args)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .expect("Failed to spawn shell");
-
-    let child_stdin = child.stdin.take().unwrap();
-    let child_stdout = child.stdout.take().unwrap();
+
+    let pty_system = NativePtySystem::default();
+    let pair = pty_system
+        .openpty(PtySize {
+            rows: 24,
+            cols: 80,
+            pixel_width: 0,
+            pixel_height: 0,
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut cmd = CommandBuilder::new(&shell);
+    cmd.args(&args);
+    let child = pair
+        .slave
+        .spawn_command(cmd)
+        .map_err(|e| e.to_string())?;
+    // The slave is owned by the child now; drop our handle so EOF propagates
+    // to the reader when the shell exits.
+    drop(pair.slave);
+
+    let reader = pair.master.try_clone_reader().map_err(|e| e.to_string())?;
+    let writer = pair.master.take_writer().map_err(|e| e.to_string())?;
     let child_arc = Arc::new(Mutex::new(child));
+    let scrollback = Arc::new(Mutex::new(Vec::new()));
 
     let session = Arc::new(TerminalSession {
         child: child_arc.clone(),
-        stdin: Arc::new(Mutex::new(child_stdin)),
+        master: Arc::new(Mutex::new(pair.master)),
+        writer: Arc::new(Mutex::new(writer)),
+        scrollback: scrollback.clone(),
     });
 
     get_sessions().insert(session_id.clone(), session.clone());
 
-    // Stream output
+    // Stream raw PTY output so escape sequences and colors survive. Chunks are
+    // base64-encoded because the bytes are not guaranteed to be valid UTF-8.
     let app_handle = app.clone();
     let sid = session_id.clone();
     std::thread::spawn(move || {
-        let reader = BufReader::new(child_stdout);
-        for line in reader.lines() {
-            match line {
-                Ok(line) => {
+        let mut reader = reader;
+        let mut buf = [0u8; 4096];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    {
+                        let mut sb = scrollback.lock().unwrap();
+                        sb.extend_from_slice(&buf[..n]);
+                        if sb.len() > SCROLLBACK_LIMIT {
+                            let excess = sb.len() - SCROLLBACK_LIMIT;
+                            sb.drain(0..excess);
+                        }
+                    }
+                    let encoded =
+                        base64::engine::general_purpose::STANDARD.encode(&buf[..n]);
                     let _ = app_handle.emit(
                         "shell-output",
                         ShellOutput {
                             session_id: sid.clone(),
-                            output: line,
+                            output: encoded,
                         },
                     );
                 }
@@ -129,7 +172,12 @@ pub async fn start_shell(app: AppHandle, session_id: String) {
             }
         }
         // Notify frontend of exit
-        let exit_status = child_arc.lock().unwrap().wait().ok().and_then(|s| s.code());
+        let exit_status = child_arc
+            .lock()
+            .unwrap()
+            .wait()
+            .ok()
+            .map(|s| s.exit_code() as i32);
         let _ = app_handle.emit(
             "shell-exit",
             ShellExit {
@@ -138,14 +186,74 @@ pub async fn start_shell(app: AppHandle, session_id: String) {
             },
         );
     });
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn resize_shell(session_id: String, rows: u16, cols: u16) -> Result<(), String> {
+    let session = get_sessions()
+        .get(&session_id)
+        .ok_or_else(|| format!("No shell for session_id {}", session_id))?
+        .clone();
+    session
+        .master
+        .lock()
+        .unwrap()
+        .resize(PtySize {
+            rows,
+            cols,
+            pixel_width: 0,
+            pixel_height: 0,
+        })
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_shells() -> Vec<ShellInfo> {
+    get_sessions()
+        .iter()
+        .map(|entry| {
+            let session = entry.value();
+            let mut child = session.child.lock().unwrap();
+            let pid = child.process_id();
+            // `try_wait` returns Some once the child has exited.
+            let alive = matches!(child.try_wait(), Ok(None));
+            ShellInfo {
+                session_id: entry.key().clone(),
+                pid,
+                alive,
+            }
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn attach_shell(app: AppHandle, session_id: String) -> Result<(), String> {
+    let session = get_sessions()
+        .get(&session_id)
+        .ok_or_else(|| format!("No shell for session_id {}", session_id))?
+        .clone();
+    // Replay the buffered scrollback so a reconnecting tab catches up instead of
+    // seeing a blank terminal.
+    let snapshot = session.scrollback.lock().unwrap().clone();
+    let encoded = base64::engine::general_purpose::STANDARD.encode(&snapshot);
+    app.emit(
+        "shell-output",
+        ShellOutput {
+            session_id: session_id.clone(),
+            output: encoded,
+        },
+    )
+    .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn send_input(session_id: String, input: String) {
     if let Some(session) = get_sessions().get(&session_id) {
-        let mut stdin = session.stdin.lock().unwrap();
-        let _ = write!(stdin, "{}", input);
-        let _ = stdin.flush();
+        let mut writer = session.writer.lock().unwrap();
+        let _ = writer.write_all(input.as_bytes());
+        let _ = writer.flush();
     }
 }
 
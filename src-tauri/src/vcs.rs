@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::Serialize;
+
+use crate::file_system::{detect_language, read_first_bytes, FileMetadata};
+
+/// Working-tree status of a tracked or untracked file, surfaced to the frontend
+/// for gutter and tree decorations.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileStatus {
+    Untracked,
+    Modified,
+    Staged,
+    Conflicted,
+}
+
+/// Abstraction over a version-control system so jeditr isn't hard-wired to a
+/// single `git` invocation and can grow support for other DVCS.
+pub trait VcsBackend {
+    /// Absolute path of the repository root.
+    fn repo_root(&self) -> &Path;
+
+    /// Tracked files enriched with language and working-tree status.
+    fn tracked_files(&self) -> Result<Vec<FileMetadata>, String>;
+
+    /// Per-path working-tree status keyed by repo-relative path.
+    fn status(&self) -> Result<HashMap<String, FileStatus>, String>;
+
+    /// Unified diff of the unstaged working-tree changes for a single path, or
+    /// an empty string when there are none; staged changes and untracked files
+    /// are not included.
+    fn diff(&self, path: &str) -> Result<String, String>;
+}
+
+/// Git implementation of [`VcsBackend`]. The repository root is detected once on
+/// construction and reused, so commands no longer depend on the process CWD.
+pub struct GitBackend {
+    root: PathBuf,
+}
+
+impl GitBackend {
+    /// Detect the enclosing repository via `git rev-parse --show-toplevel`,
+    /// starting the search from `root` rather than the process CWD.
+    pub fn discover_at(root: &Path) -> Result<Self, String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(["rev-parse", "--show-toplevel"])
+            .output()
+            .map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err("Not inside a git repository".to_string());
+        }
+        let root = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Ok(GitBackend {
+            root: PathBuf::from(root),
+        })
+    }
+
+    fn git(&self, args: &[&str]) -> Result<std::process::Output, String> {
+        Command::new("git")
+            .arg("-C")
+            .arg(&self.root)
+            .args(args)
+            .output()
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl VcsBackend for GitBackend {
+    fn repo_root(&self) -> &Path {
+        &self.root
+    }
+
+    fn tracked_files(&self) -> Result<Vec<FileMetadata>, String> {
+        let output = self.git(&["ls-files"])?;
+        if !output.status.success() {
+            return Err("Failed to run git ls-files".to_string());
+        }
+        let status = self.status()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut files = Vec::new();
+        for line in stdout.lines() {
+            let rel = line.to_string();
+            let abs = self.root.join(&rel);
+            let name = Path::new(&rel)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| rel.clone());
+            let first_bytes = if Path::new(&rel).extension().is_none() {
+                read_first_bytes(&abs)
+            } else {
+                Vec::new()
+            };
+            let language = detect_language(Path::new(&rel), &first_bytes);
+            files.push(FileMetadata {
+                path: rel.clone(),
+                name,
+                language,
+                status: status.get(&rel).copied(),
+            });
+        }
+        Ok(files)
+    }
+
+    fn status(&self) -> Result<HashMap<String, FileStatus>, String> {
+        let output = self.git(&["status", "--porcelain=v2", "-z", "--untracked-files=all"])?;
+        if !output.status.success() {
+            return Err("Failed to run git status".to_string());
+        }
+        Ok(parse_porcelain_v2(&output.stdout))
+    }
+
+    fn diff(&self, path: &str) -> Result<String, String> {
+        let output = self.git(&["diff", "--", path])?;
+        if !output.status.success() {
+            return Err(format!("Failed to diff {}", path));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+/// Parse `git status --porcelain=v2 -z` output into a per-path status map.
+///
+/// Records are NUL-separated. Paths are emitted unquoted and may contain
+/// spaces, so the path is taken as the whole remainder after a fixed number of
+/// space-delimited leading fields — never by splitting on the last space. A `2`
+/// (rename/copy) record is followed by an extra NUL-separated original path
+/// that is consumed and ignored.
+fn parse_porcelain_v2(bytes: &[u8]) -> HashMap<String, FileStatus> {
+    let mut map = HashMap::new();
+    let mut records = bytes.split(|&b| b == 0);
+    while let Some(record) = records.next() {
+        if record.is_empty() {
+            continue;
+        }
+        let record = String::from_utf8_lossy(record);
+        match record.chars().next() {
+            // Ordinary change: `1 <XY> <6 fields> <path>` — 8 leading fields.
+            Some('1') => {
+                if let (Some(xy), Some(path)) = (field(&record, 1), rest_after(&record, 8)) {
+                    map.insert(path.to_string(), classify_xy(xy));
+                }
+            }
+            // Rename/copy: `2 <XY> <7 fields> <path>` — 9 leading fields; the
+            // original path follows in the next NUL-separated token.
+            Some('2') => {
+                if let (Some(xy), Some(path)) = (field(&record, 1), rest_after(&record, 9)) {
+                    map.insert(path.to_string(), classify_xy(xy));
+                }
+                let _ = records.next();
+            }
+            // Unmerged (conflicted): `u <XY> <8 fields> <path>` — 10 leading fields.
+            Some('u') => {
+                if let Some(path) = rest_after(&record, 10) {
+                    map.insert(path.to_string(), FileStatus::Conflicted);
+                }
+            }
+            // Untracked / ignored: `? <path>` / `! <path>`.
+            Some('?') | Some('!') => {
+                if let Some(path) = rest_after(&record, 1) {
+                    map.insert(path.to_string(), FileStatus::Untracked);
+                }
+            }
+            _ => {}
+        }
+    }
+    map
+}
+
+/// The `n`-th space-delimited field of a record (0-based).
+fn field(record: &str, n: usize) -> Option<&str> {
+    record.split(' ').nth(n)
+}
+
+/// Everything after the first `n` space-delimited fields, preserving any spaces
+/// within it (i.e. the unquoted path at the end of a porcelain-v2 record).
+fn rest_after(record: &str, n: usize) -> Option<&str> {
+    record.splitn(n + 1, ' ').nth(n).filter(|s| !s.is_empty())
+}
+
+/// Map a porcelain-v2 `<XY>` staging/worktree pair to a coarse status. Staged
+/// changes (index column set) take precedence over unstaged modifications.
+fn classify_xy(xy: &str) -> FileStatus {
+    let index = xy.chars().next().unwrap_or('.');
+    if index != '.' {
+        FileStatus::Staged
+    } else {
+        FileStatus::Modified
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_xy_prefers_staged_over_worktree() {
+        assert_eq!(classify_xy("M."), FileStatus::Staged);
+        assert_eq!(classify_xy(".M"), FileStatus::Modified);
+        assert_eq!(classify_xy("MM"), FileStatus::Staged);
+    }
+
+    #[test]
+    fn parse_porcelain_v2_handles_ordinary_and_untracked() {
+        let input = "1 .M N... 100644 100644 100644 aaaa bbbb src/main.rs\0\
+                     ? notes.txt\0";
+        let map = parse_porcelain_v2(input.as_bytes());
+        assert_eq!(map.get("src/main.rs"), Some(&FileStatus::Modified));
+        assert_eq!(map.get("notes.txt"), Some(&FileStatus::Untracked));
+    }
+
+    #[test]
+    fn parse_porcelain_v2_preserves_paths_with_spaces() {
+        // Ordinary record whose path contains a space must not be truncated.
+        let input = "1 M. N... 100644 100644 100644 aaaa bbbb src/a b.rs\0";
+        let map = parse_porcelain_v2(input.as_bytes());
+        assert_eq!(map.get("src/a b.rs"), Some(&FileStatus::Staged));
+        assert!(map.get("b.rs").is_none());
+    }
+
+    #[test]
+    fn parse_porcelain_v2_consumes_rename_original_path() {
+        // `2` record: new path in the record, original path in the next token.
+        let input = "2 R. N... 100644 100644 100644 aaaa bbbb R100 new name.rs\0old name.rs\0\
+                     ? after.txt\0";
+        let map = parse_porcelain_v2(input.as_bytes());
+        assert_eq!(map.get("new name.rs"), Some(&FileStatus::Staged));
+        // The original path token must be skipped, not parsed as a record.
+        assert!(map.get("old name.rs").is_none());
+        assert_eq!(map.get("after.txt"), Some(&FileStatus::Untracked));
+    }
+
+    #[test]
+    fn parse_porcelain_v2_marks_unmerged_as_conflicted() {
+        let input =
+            "u UU N... 100644 100644 100644 100644 aaaa bbbb cccc merge me.rs\0";
+        let map = parse_porcelain_v2(input.as_bytes());
+        assert_eq!(map.get("merge me.rs"), Some(&FileStatus::Conflicted));
+    }
+}